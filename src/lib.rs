@@ -1,10 +1,23 @@
+use regex::Regex;
 use std::error::Error;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
 use std::{env, fs};
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SearchMode {
+    Substring,
+    Regex,
+}
+
 pub struct Config {
     pub query: String,
-    pub file_path: String,
+    pub paths: Vec<String>,
     pub ignore_case: bool,
+    pub mode: SearchMode,
+    pub recursive: bool,
+    pub line_number: bool,
+    pub invert_match: bool,
 }
 
 impl Config {
@@ -12,59 +25,173 @@ impl Config {
         // Throw away the first argument (application path)
         args.next();
 
-        let query = match args.next() {
+        let mut positional = Vec::new();
+        let mut ignore_case_flag = None;
+        let mut mode = SearchMode::Substring;
+        let mut recursive = false;
+        let mut line_number = false;
+        let mut invert_match = false;
+
+        for arg in args {
+            match arg.as_str() {
+                "-i" | "--ignore-case" => ignore_case_flag = Some(true),
+                "-s" | "--case-sensitive" => ignore_case_flag = Some(false),
+                "-E" | "--regex" => mode = SearchMode::Regex,
+                "-r" | "--recursive" => recursive = true,
+                "-n" | "--line-number" => line_number = true,
+                "-v" | "--invert-match" => invert_match = true,
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+
+        let query = match positional.next() {
             Some(arg) => arg,
             None => return Err("Didn't get a string to query"),
         };
 
-        let file_path = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a filepath"),
-        };
+        let paths: Vec<String> = positional.collect();
 
-        let ignore_case = env::var("IGNORE_CASE").is_ok();
+        let ignore_case = ignore_case_flag.unwrap_or_else(|| env::var("IGNORE_CASE").is_ok());
 
         Ok(Config {
             query,
-            file_path,
+            paths,
             ignore_case,
+            mode,
+            recursive,
+            line_number,
+            invert_match,
         })
     }
 }
 
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(config.file_path)?;
+    let strategy = search_strategy_factory(config.mode, config.ignore_case, &config.query)?;
+
+    if config.paths.is_empty() {
+        if io::stdin().is_terminal() {
+            return Err("no file path given and no input piped in on stdin".into());
+        }
 
-    let strategy = search_strategy_factory(config.ignore_case)
-        .expect("Should have returned a search strategy");
-    let results = strategy.search(&config.query, &contents);
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
 
-    for line in results {
-        println!("{}", line);
+        print_matches(&*strategy, &config, &contents, None);
+        return Ok(());
+    }
+
+    let files = collect_files(&config.paths, config.recursive);
+    let print_file_name = files.len() > 1;
+
+    for file in &files {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => continue,
+            Err(e) => {
+                eprintln!("minigrep: {}: {}", file.display(), e);
+                continue;
+            }
+        };
+
+        let file_name = print_file_name.then(|| file.display().to_string());
+        print_matches(&*strategy, &config, &contents, file_name.as_deref());
     }
 
     Ok(())
 }
 
-pub trait SearchStrategy {
-    fn search<'a>(&self, query: &str, contents: &'a str) -> Vec<&'a str>;
+fn print_matches(
+    strategy: &dyn SearchStrategy,
+    config: &Config,
+    contents: &str,
+    file_name: Option<&str>,
+) {
+    for (line_number, line) in strategy.search(&config.query, contents, config.invert_match) {
+        let line = if config.line_number {
+            format!("{}:{}", line_number, line)
+        } else {
+            line.to_string()
+        };
+
+        match file_name {
+            Some(file_name) => println!("{}:{}", file_name, line),
+            None => println!("{}", line),
+        }
+    }
 }
 
-#[derive(Default)]
-pub struct CaseInsensitiveSearch;
+fn collect_files(paths: &[String], recursive: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
 
-impl SearchStrategy for CaseInsensitiveSearch {
-    fn search<'a>(&self, query: &str, contents: &'a str) -> Vec<&'a str> {
-        let query = query.to_lowercase();
-        let mut results = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+
+        if path.is_dir() {
+            if recursive {
+                walk_dir(path, &mut files);
+            } else {
+                eprintln!("minigrep: {}: is a directory", path.display());
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
 
-        for line in contents.lines() {
-            if line.to_lowercase().contains(&query) {
-                results.push(line);
+    files
+}
+
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("minigrep: {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("minigrep: {}: {}", dir.display(), e);
+                continue;
             }
+        };
+
+        let path = entry.path();
+        if path.is_dir() {
+            walk_dir(&path, files);
+        } else {
+            files.push(path);
         }
+    }
+}
 
-        results
+pub trait SearchStrategy {
+    fn is_match(&self, query: &str, line: &str) -> bool;
+
+    /// Searches `contents` line by line, pairing each kept line with its
+    /// 1-based line number. When `invert` is set, lines that do *not* match
+    /// are kept instead -- implemented once here so no strategy has to
+    /// duplicate the loop.
+    fn search<'a>(&self, query: &str, contents: &'a str, invert: bool) -> Vec<(usize, &'a str)> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| self.is_match(query, line) != invert)
+            .map(|(i, line)| (i + 1, line))
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct CaseInsensitiveSearch;
+
+impl SearchStrategy for CaseInsensitiveSearch {
+    fn is_match(&self, query: &str, line: &str) -> bool {
+        line.to_lowercase().contains(&query.to_lowercase())
     }
 }
 
@@ -72,23 +199,42 @@ impl SearchStrategy for CaseInsensitiveSearch {
 pub struct CaseSensitiveSearch;
 
 impl SearchStrategy for CaseSensitiveSearch {
-    fn search<'a>(&self, query: &str, contents: &'a str) -> Vec<&'a str> {
-        let mut results: Vec<&str> = Vec::new();
+    fn is_match(&self, query: &str, line: &str) -> bool {
+        line.contains(query)
+    }
+}
 
-        for line in contents.lines() {
-            if line.contains(query) {
-                results.push(line);
-            }
-        }
+pub struct RegexSearch {
+    pattern: Regex,
+}
 
-        results
+impl SearchStrategy for RegexSearch {
+    fn is_match(&self, _query: &str, line: &str) -> bool {
+        self.pattern.is_match(line)
     }
 }
 
-pub fn search_strategy_factory(ignore_case: bool) -> Option<Box<dyn SearchStrategy>> {
-    match ignore_case {
-        false => Some(Box::new(CaseSensitiveSearch)),
-        true => Some(Box::new(CaseInsensitiveSearch)),
+pub fn search_strategy_factory(
+    mode: SearchMode,
+    ignore_case: bool,
+    query: &str,
+) -> Result<Box<dyn SearchStrategy>, String> {
+    match mode {
+        SearchMode::Substring => match ignore_case {
+            false => Ok(Box::new(CaseSensitiveSearch)),
+            true => Ok(Box::new(CaseInsensitiveSearch)),
+        },
+        SearchMode::Regex => {
+            let pattern = if ignore_case {
+                format!("(?i){}", query)
+            } else {
+                query.to_string()
+            };
+
+            let pattern = Regex::new(&pattern).map_err(|e| format!("invalid pattern: {e}"))?;
+
+            Ok(Box::new(RegexSearch { pattern }))
+        }
     }
 }
 
@@ -108,9 +254,9 @@ Trust me"
         let query = "Rust";
         let contents = get_contents();
 
-        let result = CaseSensitiveSearch.search(query, contents);
+        let result = CaseSensitiveSearch.search(query, contents, false);
 
-        assert_eq!(vec!["Rust:"], result);
+        assert_eq!(vec![(1, "Rust:")], result);
     }
 
     #[test]
@@ -118,8 +264,155 @@ Trust me"
         let query = "RuSt";
         let contents = get_contents();
 
-        let result = CaseInsensitiveSearch.search(query, contents);
+        let result = CaseInsensitiveSearch.search(query, contents, false);
+
+        assert_eq!(vec![(1, "Rust:"), (4, "Trust me")], result);
+    }
+
+    #[test]
+    fn case_sensitive_search_inverted() {
+        let query = "Rust";
+        let contents = get_contents();
+
+        let result = CaseSensitiveSearch.search(query, contents, true);
+
+        assert_eq!(
+            vec![(2, "safe, fast, productive."), (3, "Pick three."), (4, "Trust me")],
+            result
+        );
+    }
+
+    #[test]
+    fn build_ignore_case_flag_overrides_default() {
+        let args = vec![
+            String::from("minigrep"),
+            String::from("--ignore-case"),
+            String::from("RuSt"),
+            String::from("poem.txt"),
+        ];
+
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert!(config.ignore_case);
+        assert_eq!(config.query, "RuSt");
+        assert_eq!(config.paths, vec!["poem.txt"]);
+    }
+
+    #[test]
+    fn build_case_sensitive_flag_can_be_passed_after_positionals() {
+        let args = vec![
+            String::from("minigrep"),
+            String::from("Rust"),
+            String::from("poem.txt"),
+            String::from("-s"),
+        ];
+
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert!(!config.ignore_case);
+    }
+
+    #[test]
+    fn build_errors_without_query() {
+        let args = vec![String::from("minigrep")];
+
+        assert!(Config::build(args.into_iter()).is_err());
+    }
+
+    #[test]
+    fn build_regex_flag_sets_mode() {
+        let args = vec![
+            String::from("minigrep"),
+            String::from("--regex"),
+            String::from("R.st"),
+            String::from("poem.txt"),
+        ];
+
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert_eq!(config.mode, SearchMode::Regex);
+    }
+
+    #[test]
+    fn regex_search_matches_pattern() {
+        let strategy =
+            search_strategy_factory(SearchMode::Regex, false, "R.st").expect("valid pattern");
+        let contents = get_contents();
+
+        let result = strategy.search("R.st", contents, false);
+
+        assert_eq!(vec![(1, "Rust:")], result);
+    }
+
+    #[test]
+    fn regex_search_is_case_insensitive_when_requested() {
+        let strategy =
+            search_strategy_factory(SearchMode::Regex, true, "r.st").expect("valid pattern");
+        let contents = get_contents();
+
+        let result = strategy.search("r.st", contents, false);
+
+        assert_eq!(vec![(1, "Rust:"), (4, "Trust me")], result);
+    }
+
+    #[test]
+    fn regex_search_factory_reports_invalid_pattern() {
+        let result = search_strategy_factory(SearchMode::Regex, false, "(unclosed");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn build_collects_multiple_paths() {
+        let args = vec![
+            String::from("minigrep"),
+            String::from("Rust"),
+            String::from("one.txt"),
+            String::from("two.txt"),
+        ];
+
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert_eq!(config.paths, vec!["one.txt", "two.txt"]);
+    }
+
+    #[test]
+    fn build_recursive_flag_is_parsed_as_an_option() {
+        let args = vec![
+            String::from("minigrep"),
+            String::from("Rust"),
+            String::from("--recursive"),
+            String::from("src"),
+        ];
+
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert!(config.recursive);
+        assert_eq!(config.paths, vec!["src"]);
+    }
+
+    #[test]
+    fn build_allows_missing_file_path_for_stdin_fallback() {
+        let args = vec![String::from("minigrep"), String::from("Rust")];
+
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert!(config.paths.is_empty());
+    }
+
+    #[test]
+    fn build_line_number_and_invert_match_flags_are_parsed() {
+        let args = vec![
+            String::from("minigrep"),
+            String::from("--line-number"),
+            String::from("Rust"),
+            String::from("-v"),
+            String::from("poem.txt"),
+        ];
+
+        let config = Config::build(args.into_iter()).unwrap();
 
-        assert_eq!(vec!["Rust:", "Trust me"], result);
+        assert!(config.line_number);
+        assert!(config.invert_match);
     }
 }